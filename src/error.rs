@@ -7,6 +7,9 @@ pub enum Error {
     Io(io::Error),
     Serial(tokio_serial::Error),
     Reqwest(reqwest::Error),
+    Json(serde_json::Error),
+    Broker(String),
+    Batch,
 }
 
 impl Display for Error {
@@ -15,6 +18,9 @@ impl Display for Error {
             Error::Io(e) => write!(f, "Input/Output error: {}.", e),
             Error::Serial(e) => write!(f, "Serial port error: {}.", e),
             Error::Reqwest(e) => write!(f, "HTTP error: {}.", e),
+            Error::Json(e) => write!(f, "JSON error: {}.", e),
+            Error::Broker(e) => write!(f, "Broker error: {}.", e),
+            Error::Batch => write!(f, "Server rejected a batched reading."),
         }
     }
 }
@@ -38,3 +44,9 @@ impl From<reqwest::Error> for Error {
         Error::Reqwest(err)
     }
 }
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
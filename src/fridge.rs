@@ -0,0 +1,115 @@
+use crate::error::Error;
+use crate::params::ParamsHandle;
+use crate::Reading;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+use tokio_serial::SerialPortBuilderExt;
+
+/// Hysteresis controller that drives a cooling relay off the thermometer
+/// stream, protecting the compressor from rapid cycling.
+///
+/// The relay switches ON once the temperature rises above `target +
+/// upper_band`, and OFF once it drops below `target - lower_band`. Either
+/// transition is held off until the relay has kept its current state for at
+/// least `min_on`/`min_off`, even if the temperature re-crosses the band in
+/// the meantime.
+///
+/// A stale or missing reading (e.g. a read timeout upstream) simply means no
+/// message arrives on `rx`, so the relay holds its current state with no
+/// extra handling needed.
+///
+/// `relay_enabled` is re-read from `params` on every reading rather than
+/// once at startup, so it can be flipped via the watched config file like
+/// every other hot-reloadable parameter. Disabling it switches the relay
+/// off (compressor protection wins over leaving it running unsupervised)
+/// and holds it off until it's re-enabled.
+pub async fn run(
+    mut rx: UnboundedReceiver<Reading>,
+    tx: UnboundedSender<Reading>,
+    params: Arc<ParamsHandle>,
+) {
+    let mut relay_on = false;
+    let mut last_switch = Instant::now();
+
+    while let Some(reading) = rx.recv().await {
+        let Reading::Thermometer { temperature, .. } = reading else {
+            continue;
+        };
+
+        let current = params.load();
+
+        if !current.relay_enabled {
+            if relay_on {
+                switch_relay(
+                    &current.relay_path,
+                    false,
+                    &mut relay_on,
+                    &mut last_switch,
+                    &tx,
+                )
+                .await;
+            }
+            continue;
+        }
+
+        let held_for = last_switch.elapsed();
+        let should_turn_on = !relay_on
+            && temperature > current.target + current.upper_band
+            && held_for >= current.min_off;
+        let should_turn_off = relay_on
+            && temperature < current.target - current.lower_band
+            && held_for >= current.min_on;
+        if !should_turn_on && !should_turn_off {
+            continue;
+        }
+
+        switch_relay(
+            &current.relay_path,
+            !relay_on,
+            &mut relay_on,
+            &mut last_switch,
+            &tx,
+        )
+        .await;
+    }
+}
+
+/// Attempts to drive the relay to `desired`, only updating `relay_on` (and
+/// reporting the change) if the actuation actually succeeds -- otherwise the
+/// in-memory state would claim a transition that never happened physically,
+/// with no way to notice or resync later.
+async fn switch_relay(
+    relay_path: &str,
+    desired: bool,
+    relay_on: &mut bool,
+    last_switch: &mut Instant,
+    tx: &UnboundedSender<Reading>,
+) {
+    if let Err(e) = actuate(relay_path, desired).await {
+        tracing::error!(error = %e, relay_on = desired, "relay actuation failed, leaving state unchanged");
+        return;
+    }
+
+    *relay_on = desired;
+    *last_switch = Instant::now();
+    let reading = Reading::Relay {
+        time: OffsetDateTime::now_utc(),
+        on: desired,
+    };
+    tx.send(reading).ok();
+}
+
+/// Writes the relay command to the configured serial port. A GPIO backend
+/// could be slotted in here the same way; actuation is skipped entirely when
+/// no `relay_path` is configured.
+async fn actuate(relay_path: &str, on: bool) -> Result<(), Error> {
+    if relay_path.is_empty() {
+        return Ok(());
+    }
+    let mut serial = tokio_serial::new(relay_path, 9600).open_native_async()?;
+    serial.write_u8(if on { b'1' } else { b'0' }).await?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use crate::error::Error;
+use crate::Reading;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+
+/// Durable write-ahead log for readings that couldn't be uploaded.
+///
+/// Entries are appended as line-delimited JSON, so a reading survives a
+/// crash or restart while the collector is unreachable and can be replayed
+/// once it comes back. `append` and [`Journal::replay`] share a lock so a
+/// reading can't be appended mid-line and can't be lost to a replay's
+/// rewrite racing with a concurrent append.
+#[derive(Clone)]
+pub struct Journal {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Journal {
+            path: path.into(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Appends a reading to the journal.
+    pub async fn append(&self, reading: &Reading) -> Result<(), Error> {
+        let line = serde_json::to_string(reading)?;
+        let _guard = self.lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.write_all(b"\n").await?;
+        Ok(())
+    }
+
+    /// Loads every buffered reading, oldest first, then hands them to `f` and
+    /// persists whatever it reports as still pending.
+    ///
+    /// The load, `f`, and the rewrite all run under the journal's lock, so an
+    /// `append` that arrives while `f` is in flight (e.g. doing an HTTP round
+    /// trip) is held off instead of being silently erased by the rewrite.
+    /// Returns `None` if the journal was empty, so callers can tell "nothing
+    /// to replay" apart from "replayed everything".
+    pub async fn replay<F, Fut>(&self, f: F) -> Result<Option<Vec<Reading>>, Error>
+    where
+        F: FnOnce(Vec<Reading>) -> Fut,
+        Fut: Future<Output = Vec<Reading>>,
+    {
+        let _guard = self.lock.lock().await;
+
+        let readings = self.read_all().await?;
+        if readings.is_empty() {
+            return Ok(None);
+        }
+
+        let still_pending = f(readings).await;
+
+        let mut data = String::new();
+        for reading in &still_pending {
+            data.push_str(&serde_json::to_string(reading)?);
+            data.push('\n');
+        }
+        tokio::fs::write(&self.path, data).await?;
+
+        Ok(Some(still_pending))
+    }
+
+    async fn read_all(&self) -> Result<Vec<Reading>, Error> {
+        let file = match File::open(&self.path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut lines = BufReader::new(file).lines();
+        let mut readings = Vec::new();
+        while let Some(line) = lines.next_line().await? {
+            match serde_json::from_str(&line) {
+                Ok(reading) => readings.push(reading),
+                Err(e) => tracing::error!(error = %e, "dropping corrupt journal entry"),
+            }
+        }
+        Ok(readings)
+    }
+}
@@ -1,7 +1,12 @@
 use error::Error;
+use journal::Journal;
+use params::{Params, ParamsHandle};
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sink::{BrokerSink, HttpSink, ReadingSink};
 use std::io::{self, ErrorKind};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use std::{env, str};
 use time::OffsetDateTime;
@@ -10,8 +15,13 @@ use tokio::runtime::Builder;
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use tokio::time::Instant;
 use tokio_serial::SerialPortBuilderExt;
+use tracing::Instrument;
 
 mod error;
+mod fridge;
+mod journal;
+mod params;
+mod sink;
 
 struct SensorReading {
     temperature: f32,
@@ -35,6 +45,7 @@ struct Sensor {
 }
 
 impl Sensor {
+    #[tracing::instrument(skip(self), fields(path = %self.path, baud_rate = self.baud_rate))]
     async fn read(&self) -> Result<SensorReading, Error> {
         let mut serial = tokio_serial::new(&self.path, self.baud_rate).open_native_async()?;
         serial.write_u8(b'M').await?;
@@ -53,12 +64,28 @@ impl Sensor {
 async fn co2_thread(
     sensor: co2mon::Sensor,
     tx: UnboundedSender<Reading>,
+    params: Arc<ParamsHandle>,
 ) -> Result<(), Box<dyn std::error::Error + Send>> {
-    let mut interval = tokio::time::interval_at(Instant::now(), Duration::from_secs(10));
+    let mut epoch = params.epoch();
+    let mut interval = tokio::time::interval_at(Instant::now(), params.load().poll_interval);
     loop {
         interval.tick().await;
+
+        let current_epoch = params.epoch();
+        if current_epoch != epoch {
+            epoch = current_epoch;
+            interval = tokio::time::interval_at(Instant::now(), params.load().poll_interval);
+        }
+
+        let span = tracing::info_span!("co2_tick");
+        let _enter = span.enter();
         match sensor.read() {
             Ok(reading) => {
+                tracing::debug!(
+                    temperature = reading.temperature(),
+                    co2 = reading.co2(),
+                    "co2 reading"
+                );
                 let reading = Reading::Co2Meter {
                     time: OffsetDateTime::now_utc(),
                     temperature: reading.temperature(),
@@ -66,36 +93,51 @@ async fn co2_thread(
                 };
                 tx.send(reading).unwrap();
             }
-            Err(e) => eprintln!("{}", e),
+            Err(e) => tracing::warn!(error = %e, "co2 sensor read failed"),
         }
     }
 }
 
-#[derive(Debug)]
-enum Reading {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum Reading {
     Thermometer {
+        #[serde(with = "time::serde::rfc3339")]
         time: OffsetDateTime,
         temperature: f32,
         humidity: f32,
     },
     Co2Meter {
+        #[serde(with = "time::serde::rfc3339")]
         time: OffsetDateTime,
         temperature: f32,
         co2: u16,
     },
+    Relay {
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+        on: bool,
+    },
 }
 
-async fn save_reading(
+#[tracing::instrument(
+    skip(client, api_config, reading),
+    fields(kind = tracing::field::Empty, temperature = tracing::field::Empty, humidity = tracing::field::Empty, co2 = tracing::field::Empty, on = tracing::field::Empty),
+)]
+pub(crate) async fn save_reading(
     client: &Client,
     api_config: &ApiConfig,
-    reading: Reading,
+    reading: &Reading,
 ) -> Result<(), Error> {
-    match reading {
+    let span = tracing::Span::current();
+    let result = match reading {
         Reading::Thermometer {
             time,
             temperature,
             humidity,
         } => {
+            span.record("kind", "thermometer");
+            span.record("temperature", *temperature as f64);
+            span.record("humidity", *humidity as f64);
             client
                 .post(format!(
                     "{}stats?time={}&temperature={}&humidity={}",
@@ -107,13 +149,16 @@ async fn save_reading(
                 .bearer_auth(&api_config.access_token)
                 .send()
                 .await?
-                .error_for_status()?;
+                .error_for_status()
         }
         Reading::Co2Meter {
             time,
             temperature,
             co2,
         } => {
+            span.record("kind", "co2");
+            span.record("temperature", *temperature as f64);
+            span.record("co2", *co2 as u64);
             client
                 .post(format!(
                     "{}stats2?time={}&temperature={}&co2={}",
@@ -125,47 +170,236 @@ async fn save_reading(
                 .bearer_auth(&api_config.access_token)
                 .send()
                 .await?
-                .error_for_status()?;
+                .error_for_status()
         }
+        Reading::Relay { time, on } => {
+            span.record("kind", "relay");
+            span.record("on", *on);
+            client
+                .post(format!(
+                    "{}relay?time={}&on={}",
+                    api_config.api_url,
+                    time.unix_timestamp(),
+                    on
+                ))
+                .bearer_auth(&api_config.access_token)
+                .send()
+                .await?
+                .error_for_status()
+        }
+    };
+    if let Err(e) = &result {
+        tracing::warn!(error = %e, "HTTP upload failed");
     }
+    result?;
     Ok(())
 }
 
-async fn db_thread(api_config: ApiConfig, mut rx: UnboundedReceiver<Reading>) {
-    let client = Client::new();
-    while let Some(reading) = rx.recv().await {
-        if let Err(e) = save_reading(&client, &api_config, reading).await {
-            eprintln!("{}", e);
+async fn db_thread(
+    sinks: Vec<Arc<dyn ReadingSink>>,
+    journal: Journal,
+    params: Arc<ParamsHandle>,
+    mut rx: UnboundedReceiver<Reading>,
+) {
+    let mut batch = Vec::new();
+    let mut epoch = params.epoch();
+    let mut flush_interval = tokio::time::interval(params.load().batch_flush_interval);
+    let mut in_flight = Vec::new();
+
+    loop {
+        let current_epoch = params.epoch();
+        if current_epoch != epoch {
+            epoch = current_epoch;
+            flush_interval = tokio::time::interval(params.load().batch_flush_interval);
+        }
+
+        tokio::select! {
+            reading = rx.recv() => {
+                let Some(reading) = reading else { break };
+                batch.push(reading);
+                if batch.len() >= params.load().batch_size {
+                    flush_batch(&mut batch, &sinks, &journal, &params, &mut in_flight).await;
+                }
+            }
+            _ = flush_interval.tick() => {
+                flush_batch(&mut batch, &sinks, &journal, &params, &mut in_flight).await;
+            }
+        }
+    }
+
+    flush_batch(&mut batch, &sinks, &journal, &params, &mut in_flight).await;
+    for handle in in_flight {
+        handle.await.ok();
+    }
+}
+
+/// Hands the accumulated batch off for sending, either awaiting it right
+/// away (`sequence`) or letting it run concurrently with the next batch.
+///
+/// With `sequence` left at its default of `false`, several `send_batch`
+/// tasks are routinely in flight together, each able to call
+/// `journal.append()` at the same time as the others and as the replay
+/// thread's rewrite cycle. That's safe only because `Journal` serializes
+/// appends and replays internally; see its doc comment.
+async fn flush_batch(
+    batch: &mut Vec<Reading>,
+    sinks: &[Arc<dyn ReadingSink>],
+    journal: &Journal,
+    params: &Arc<ParamsHandle>,
+    in_flight: &mut Vec<tokio::task::JoinHandle<()>>,
+) {
+    if batch.is_empty() {
+        return;
+    }
+    let readings = std::mem::take(batch);
+
+    if params.load().sequence {
+        send_batch(readings, sinks.to_vec(), journal.clone()).await;
+    } else {
+        in_flight.retain(|handle| !handle.is_finished());
+        in_flight.push(tokio::spawn(send_batch(
+            readings,
+            sinks.to_vec(),
+            journal.clone(),
+        )));
+    }
+}
+
+async fn send_batch(readings: Vec<Reading>, sinks: Vec<Arc<dyn ReadingSink>>, journal: Journal) {
+    for sink in &sinks {
+        let results = sink.publish_batch(&readings).await;
+        for (reading, result) in readings.iter().zip(results) {
+            if let Err(e) = result {
+                match e {
+                    Error::Reqwest(_) | Error::Io(_) => {
+                        tracing::warn!(error = %e, "upload failed, buffering to journal");
+                        if let Err(e) = journal.append(reading).await {
+                            tracing::error!(error = %e, "failed to append to journal");
+                        }
+                    }
+                    _ => tracing::error!(error = %e, "sink publish failed"),
+                }
+            }
         }
     }
 }
 
-async fn run(api_config: ApiConfig, tty_path: String) {
-    let temperature_sensor = Sensor {
-        path: tty_path,
-        baud_rate: 9600,
-    };
+/// Drains the journal in timestamp (append) order whenever the collector is
+/// reachable again, backing off exponentially between failed attempts.
+async fn replay_thread(access_token: String, params: Arc<ParamsHandle>, journal: Journal) {
+    let sink = HttpSink::new(access_token, params);
+    let min_backoff = Duration::from_secs(1);
+    let max_backoff = Duration::from_secs(60);
+    let mut backoff = min_backoff;
+    loop {
+        tokio::time::sleep(backoff).await;
+
+        // Replay in a single batch request, same as db_thread, so catching
+        // up on a large backlog doesn't reopen one connection per reading.
+        // The load, publish, and rewrite all happen under the journal's
+        // lock, so a reading appended mid-replay is held off instead of
+        // being erased by the rewrite.
+        let result = journal
+            .replay(|pending| async {
+                let results = sink.publish_batch(&pending).await;
+                let mut still_pending = Vec::new();
+                for (reading, result) in pending.into_iter().zip(results) {
+                    if let Err(e) = result {
+                        tracing::warn!(
+                            error = %e,
+                            "journal replay entry failed, keeping it buffered"
+                        );
+                        still_pending.push(reading);
+                    }
+                }
+                still_pending
+            })
+            .await;
+
+        let still_pending = match result {
+            Ok(None) => {
+                backoff = min_backoff;
+                continue;
+            }
+            Ok(Some(still_pending)) => still_pending,
+            Err(e) => {
+                tracing::error!(error = %e, "failed to replay journal");
+                continue;
+            }
+        };
+
+        let replayed = still_pending.is_empty();
+        backoff = if replayed {
+            min_backoff
+        } else {
+            (backoff * 2).min(max_backoff)
+        };
+        if replayed {
+            tracing::info!("journal replay caught up");
+        }
+    }
+}
+
+async fn run(
+    access_token: String,
+    params: Arc<ParamsHandle>,
+    params_path: String,
+    tty_path: String,
+    journal_path: String,
+) {
+    let journal = Journal::new(journal_path);
 
     let (tx, rx) = mpsc::unbounded_channel();
 
-    tokio::spawn(async move {
-        db_thread(api_config, rx).await;
-    });
+    let mut sinks: Vec<Arc<dyn ReadingSink>> = vec![Arc::new(HttpSink::new(
+        access_token.clone(),
+        params.clone(),
+    ))];
+    let nats_url = params.load().nats_url.clone();
+    if !nats_url.is_empty() {
+        match BrokerSink::connect(&nats_url).await {
+            Ok(sink) => sinks.push(Arc::new(sink)),
+            Err(e) => tracing::error!(error = %e, "failed to connect to broker"),
+        }
+    }
+
+    tokio::spawn(db_thread(sinks, journal.clone(), params.clone(), rx));
+    tokio::spawn(replay_thread(access_token, params.clone(), journal));
+    tokio::spawn(params::watch(params_path, params.clone()));
 
     match co2mon::Sensor::open_default() {
         Ok(sensor) => {
-            tokio::spawn(co2_thread(sensor, tx.clone()));
+            tokio::spawn(co2_thread(sensor, tx.clone(), params.clone()));
         }
         Err(e) => {
-            eprintln!("{}", e);
+            tracing::error!(error = %e, "failed to open co2 sensor");
         }
     };
 
-    let mut interval = tokio::time::interval_at(Instant::now(), Duration::from_secs(10));
+    // Always spawned, same as the other hot-reloadable subsystems:
+    // `fridge::run` re-checks `relay_enabled` on every reading, so toggling
+    // it in the watched config file takes effect without a restart.
+    let (fridge_tx, fridge_rx) = mpsc::unbounded_channel();
+    tokio::spawn(fridge::run(fridge_rx, tx.clone(), params.clone()));
+
+    let mut epoch = params.epoch();
+    let mut interval = tokio::time::interval_at(Instant::now(), params.load().poll_interval);
     loop {
         interval.tick().await;
-        let temperature_sensor = temperature_sensor.clone();
+
+        let current = params.load();
+        let current_epoch = params.epoch();
+        if current_epoch != epoch {
+            epoch = current_epoch;
+            interval = tokio::time::interval_at(Instant::now(), current.poll_interval);
+        }
+
+        let temperature_sensor = Sensor {
+            path: tty_path.clone(),
+            baud_rate: current.baud_rate,
+        };
         let tx = tx.clone();
+        let fridge_tx = fridge_tx.clone();
         let one = async move {
             let reading = temperature_sensor.read().await?;
             let reading = Reading::Thermometer {
@@ -173,36 +407,72 @@ async fn run(api_config: ApiConfig, tty_path: String) {
                 temperature: reading.temperature,
                 humidity: reading.humidity,
             };
+            fridge_tx.send(reading.clone()).ok();
             tx.send(reading).unwrap();
             Ok::<(), Error>(())
-        };
-        if let Err(e) = tokio::time::timeout(Duration::from_secs(6), one).await {
-            eprintln!("{}", e);
+        }
+        .instrument(tracing::info_span!("thermometer_tick"));
+        match tokio::time::timeout(current.read_timeout, one).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!(error = %e, "thermometer read failed"),
+            Err(elapsed) => {
+                tracing::warn!(timeout = ?current.read_timeout, %elapsed, "thermometer read timed out")
+            }
         }
     }
 }
 
-struct ApiConfig {
-    api_url: String,
-    access_token: String,
+#[derive(Clone)]
+pub(crate) struct ApiConfig {
+    pub(crate) api_url: String,
+    pub(crate) access_token: String,
+}
+
+/// Sets up the global tracing subscriber: `RUST_LOG`-filtered console output
+/// by default, or newline-delimited JSON when `TRACING_FORMAT=json` is set,
+/// for operators feeding a log aggregator instead of a terminal.
+fn init_tracing() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    if env::var("TRACING_FORMAT").as_deref() == Ok("json") {
+        subscriber.json().init();
+    } else {
+        subscriber.init();
+    }
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    init_tracing();
+
     let mut args = env::args();
     let arg = args.nth(1);
     let tty_path = arg.as_deref().unwrap_or("/dev/ttyACM0").to_string();
-    let api_url = env::var("API_URL")?;
     let access_token = env::var("ACCESS_TOKEN")?;
+    let journal_path = env::var("JOURNAL_PATH").unwrap_or_else(|_| "readings.journal".to_string());
+    let params_path = env::var("PARAMS_PATH").unwrap_or_else(|_| "params.conf".to_string());
 
-    let api_config = ApiConfig {
-        api_url,
-        access_token,
+    let params = match Params::load(&params_path) {
+        Ok(params) => params,
+        Err(e) => {
+            tracing::warn!(error = %e, path = %params_path, "failed to load params file, using defaults");
+            Params::default()
+        }
     };
+    if params.api_url.is_empty() {
+        return Err(format!(
+            "api_url is not set; add it to {params_path} before starting"
+        )
+        .into());
+    }
+    let params = Arc::new(ParamsHandle::new(params));
 
     let rt = Builder::new_current_thread()
         .enable_io()
         .enable_time()
         .build()?;
-    rt.block_on(async move { run(api_config, tty_path).await });
+    rt.block_on(
+        async move { run(access_token, params, params_path, tty_path, journal_path).await },
+    );
     Ok(())
 }
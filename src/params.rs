@@ -0,0 +1,215 @@
+use crate::error::Error;
+use arc_swap::ArcSwap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Runtime-tunable parameters, reloaded from a config file without
+/// restarting the process.
+///
+/// Secrets (the API access token) stay in the environment; everything here
+/// is safe to edit in the field while the sensor is running.
+#[derive(Debug, Clone)]
+pub struct Params {
+    pub api_url: String,
+    pub baud_rate: u32,
+    pub poll_interval: Duration,
+    pub read_timeout: Duration,
+
+    /// Enables the hysteresis relay controller in [`crate::fridge`].
+    pub relay_enabled: bool,
+    /// Serial port the relay command is written to; actuation is skipped
+    /// when empty.
+    pub relay_path: String,
+    pub target: f32,
+    pub upper_band: f32,
+    pub lower_band: f32,
+    pub min_on: Duration,
+    pub min_off: Duration,
+
+    /// NATS server URL readings are also published to; the broker sink is
+    /// disabled when empty. Only NATS is supported -- see
+    /// [`crate::sink::BrokerSink`]. Read once at startup: a reloaded value
+    /// takes effect only after a restart.
+    pub nats_url: String,
+
+    /// Readings accumulate into a batch until it reaches this size...
+    pub batch_size: usize,
+    /// ...or until this much time has passed, whichever comes first.
+    pub batch_flush_interval: Duration,
+    /// Forces batches to be sent one at a time, in order, instead of
+    /// letting multiple batch requests be in flight concurrently.
+    pub sequence: bool,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            api_url: String::new(),
+            baud_rate: 9600,
+            poll_interval: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(6),
+
+            relay_enabled: false,
+            relay_path: String::new(),
+            target: 4.0,
+            upper_band: 1.0,
+            lower_band: 1.0,
+            min_on: Duration::from_secs(300),
+            min_off: Duration::from_secs(300),
+
+            nats_url: String::new(),
+
+            batch_size: 20,
+            batch_flush_interval: Duration::from_secs(5),
+            sequence: false,
+        }
+    }
+}
+
+impl Params {
+    /// Parses a simple `key = value` config file, falling back to the
+    /// default for any field that's missing or malformed.
+    ///
+    /// `*_secs` durations that feed a `tokio::time::interval` are clamped to
+    /// a minimum of one second -- `interval`/`interval_at` panic on a zero
+    /// period. In `run`, which `rt.block_on` drives directly, that panic
+    /// would tear down the whole process; in a spawned task such as
+    /// `db_thread` or `co2_thread` it would silently end that task.
+    pub fn load(path: impl AsRef<Path>) -> Result<Params, Error> {
+        let text = std::fs::read_to_string(path)?;
+        let mut params = Params::default();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "api_url" => params.api_url = value.to_string(),
+                "baud_rate" => {
+                    if let Ok(v) = value.parse() {
+                        params.baud_rate = v;
+                    }
+                }
+                "poll_interval_secs" => {
+                    if let Ok(v) = value.parse::<u64>() {
+                        params.poll_interval = Duration::from_secs(v.max(1));
+                    }
+                }
+                "read_timeout_secs" => {
+                    if let Ok(v) = value.parse::<u64>() {
+                        params.read_timeout = Duration::from_secs(v.max(1));
+                    }
+                }
+                "relay_enabled" => {
+                    if let Ok(v) = value.parse() {
+                        params.relay_enabled = v;
+                    }
+                }
+                "relay_path" => params.relay_path = value.to_string(),
+                "target" => {
+                    if let Ok(v) = value.parse() {
+                        params.target = v;
+                    }
+                }
+                "upper_band" => {
+                    if let Ok(v) = value.parse() {
+                        params.upper_band = v;
+                    }
+                }
+                "lower_band" => {
+                    if let Ok(v) = value.parse() {
+                        params.lower_band = v;
+                    }
+                }
+                "min_on_secs" => {
+                    if let Ok(v) = value.parse() {
+                        params.min_on = Duration::from_secs(v);
+                    }
+                }
+                "min_off_secs" => {
+                    if let Ok(v) = value.parse() {
+                        params.min_off = Duration::from_secs(v);
+                    }
+                }
+                "nats_url" => params.nats_url = value.to_string(),
+                "batch_size" => {
+                    if let Ok(v) = value.parse() {
+                        params.batch_size = v;
+                    }
+                }
+                "batch_flush_interval_secs" => {
+                    if let Ok(v) = value.parse::<u64>() {
+                        params.batch_flush_interval = Duration::from_secs(v.max(1));
+                    }
+                }
+                "sequence" => {
+                    if let Ok(v) = value.parse() {
+                        params.sequence = v;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Holds the current `Params` plus an epoch counter that's bumped on every
+/// reload, so long-running loops can tell a stale snapshot from a fresh one
+/// without comparing the whole struct.
+pub struct ParamsHandle {
+    params: ArcSwap<Params>,
+    epoch: AtomicU64,
+}
+
+impl ParamsHandle {
+    pub fn new(params: Params) -> Self {
+        ParamsHandle {
+            params: ArcSwap::from_pointee(params),
+            epoch: AtomicU64::new(0),
+        }
+    }
+
+    pub fn load(&self) -> Arc<Params> {
+        self.params.load_full()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    fn swap(&self, params: Params) {
+        self.params.store(Arc::new(params));
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Polls the config file for changes and swaps in a fresh `Params` whenever
+/// its modification time advances.
+pub async fn watch(path: impl AsRef<Path>, handle: Arc<ParamsHandle>) {
+    let path = path.as_ref();
+    let mut last_modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    loop {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+
+        let modified = match std::fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(_) => continue,
+        };
+        if Some(modified) == last_modified {
+            continue;
+        }
+        last_modified = Some(modified);
+
+        match Params::load(path) {
+            Ok(params) => handle.swap(params),
+            Err(e) => tracing::warn!(error = %e, "failed to reload params"),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::error::Error;
+use crate::params::ParamsHandle;
+use crate::{save_reading, ApiConfig, Reading};
+use async_trait::async_trait;
+use reqwest::Client;
+use std::sync::Arc;
+
+/// Destination a `Reading` can be published to. `db_thread` fans each
+/// reading out to every configured sink.
+#[async_trait]
+pub trait ReadingSink: Send + Sync {
+    async fn publish(&self, reading: &Reading) -> Result<(), Error>;
+
+    /// Publishes a batch, one result per reading in submission order.
+    ///
+    /// The default just calls `publish` for each reading in turn; sinks
+    /// with a real batch endpoint (like `HttpSink`) should override this to
+    /// send everything in a single round trip.
+    async fn publish_batch(&self, readings: &[Reading]) -> Vec<Result<(), Error>> {
+        let mut results = Vec::with_capacity(readings.len());
+        for reading in readings {
+            results.push(self.publish(reading).await);
+        }
+        results
+    }
+}
+
+/// Posts readings to the `stats`/`stats2` HTTP API, same as before sinks
+/// existed. Tracks `params` so a reloaded `api_url` is picked up immediately.
+pub struct HttpSink {
+    client: Client,
+    access_token: String,
+    params: Arc<ParamsHandle>,
+}
+
+impl HttpSink {
+    pub fn new(access_token: String, params: Arc<ParamsHandle>) -> Self {
+        HttpSink {
+            client: Client::new(),
+            access_token,
+            params,
+        }
+    }
+
+    async fn publish_each(&self, readings: &[Reading]) -> Vec<Result<(), Error>> {
+        let mut results = Vec::with_capacity(readings.len());
+        for reading in readings {
+            results.push(self.publish(reading).await);
+        }
+        results
+    }
+}
+
+#[async_trait]
+impl ReadingSink for HttpSink {
+    async fn publish(&self, reading: &Reading) -> Result<(), Error> {
+        let api_config = ApiConfig {
+            api_url: self.params.load().api_url.clone(),
+            access_token: self.access_token.clone(),
+        };
+        save_reading(&self.client, &api_config, reading).await
+    }
+
+    async fn publish_batch(&self, readings: &[Reading]) -> Vec<Result<(), Error>> {
+        if readings.is_empty() {
+            return Vec::new();
+        }
+
+        let api_config = ApiConfig {
+            api_url: self.params.load().api_url.clone(),
+            access_token: self.access_token.clone(),
+        };
+        match post_batch(&self.client, &api_config, readings).await {
+            Ok(accepted) if accepted.len() == readings.len() => accepted
+                .into_iter()
+                .map(|ok| if ok { Ok(()) } else { Err(Error::Batch) })
+                .collect(),
+            Ok(accepted) => {
+                tracing::warn!(
+                    sent = readings.len(),
+                    accepted = accepted.len(),
+                    "batch response length mismatch, falling back to per-reading upload"
+                );
+                self.publish_each(readings).await
+            }
+            // The batch request failed (network error, timeout, non-2xx
+            // response, unparseable reply) -- fall back to posting readings
+            // one at a time so each keeps its own real error and can still
+            // be buffered to the journal when appropriate.
+            Err(_) => self.publish_each(readings).await,
+        }
+    }
+}
+
+/// Posts a batch to `stats/batch` and returns the per-reading acceptance
+/// flags the server replies with, in submission order.
+async fn post_batch(
+    client: &Client,
+    api_config: &ApiConfig,
+    readings: &[Reading],
+) -> Result<Vec<bool>, Error> {
+    let accepted = client
+        .post(format!("{}stats/batch", api_config.api_url))
+        .bearer_auth(&api_config.access_token)
+        .json(readings)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<bool>>()
+        .await?;
+    Ok(accepted)
+}
+
+fn subject(reading: &Reading) -> &'static str {
+    match reading {
+        Reading::Thermometer { .. } => "sensors.thermometer",
+        Reading::Co2Meter { .. } => "sensors.co2",
+        Reading::Relay { .. } => "sensors.relay",
+    }
+}
+
+/// Publishes readings as JSON to a NATS subject so dashboards can subscribe
+/// in real time instead of polling the stats API.
+///
+/// Speaks the NATS wire protocol only; an `mqtt://` broker URL will fail to
+/// connect. `subject` doubles as an MQTT topic name if a real MQTT client
+/// is ever slotted in behind this same trait.
+pub struct BrokerSink {
+    client: async_nats::Client,
+}
+
+impl BrokerSink {
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| Error::Broker(e.to_string()))?;
+        Ok(BrokerSink { client })
+    }
+}
+
+#[async_trait]
+impl ReadingSink for BrokerSink {
+    async fn publish(&self, reading: &Reading) -> Result<(), Error> {
+        let payload = serde_json::to_vec(reading)?;
+        self.client
+            .publish(subject(reading), payload.into())
+            .await
+            .map_err(|e| Error::Broker(e.to_string()))?;
+        Ok(())
+    }
+}